@@ -1,57 +1,622 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command};
 use std::sync::Mutex;
+use clap::Parser;
 use tauri::{AppHandle, Manager, State, WindowEvent};
 use tokio::time::{sleep, Duration};
-use actix_web::{App, HttpServer};
+#[cfg(feature = "actix-web-server")]
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+#[cfg(feature = "actix-web-server")]
 use actix_files as fs;
 
+/// Which parts of the launch sequence this build/run owns. Selected at
+/// compile time by Cargo feature (one of `embedded-server`,
+/// `external-server`, `no-server`), but overridable at runtime via
+/// `--server-mode` / `server_mode` in the config file, or the legacy
+/// `--no-backend` / `--external-backend` flags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ServerMode {
+    /// Spawn and own the backend process and the bundled web server (the
+    /// original, fully self-contained behavior).
+    Embedded,
+    /// Connect to an already-running backend/web deployment; never spawn
+    /// anything locally.
+    External,
+    /// UI only - don't spawn a backend and don't run the bundled web
+    /// server at all.
+    NoServer,
+}
+
+impl ServerMode {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "embedded-server" | "embedded" => Some(Self::Embedded),
+            "external-server" | "external" => Some(Self::External),
+            "no-server" | "none" => Some(Self::NoServer),
+            _ => None,
+        }
+    }
+
+    /// The mode baked in at compile time via Cargo features, used when
+    /// nothing at runtime overrides it. `no-server` takes precedence over
+    /// `external-server`; with neither enabled the mode is `Embedded`,
+    /// matching the crate's historical behavior.
+    fn compile_time_default() -> Self {
+        if cfg!(feature = "no-server") {
+            Self::NoServer
+        } else if cfg!(feature = "external-server") {
+            Self::External
+        } else {
+            Self::Embedded
+        }
+    }
+}
+
+/// CLI flags accepted alongside (and overriding) the resolved config file.
+#[derive(Parser, Debug)]
+#[command(name = "dipeo-desktop", about = "DiPeO desktop shell")]
+struct CliArgs {
+    /// Port the bundled web server / control API listens on.
+    #[arg(long)]
+    web_port: Option<u16>,
+    /// Port the backend GraphQL server listens on.
+    #[arg(long)]
+    backend_port: Option<u16>,
+    /// Address to bind local servers to.
+    #[arg(long)]
+    bind: Option<String>,
+    /// Don't spawn the bundled backend process at all.
+    #[arg(long)]
+    no_backend: bool,
+    /// Connect to an already-running backend at this URL instead of spawning one.
+    #[arg(long)]
+    external_backend: Option<String>,
+    /// Connect to an already-running web deployment instead of the bundled UI.
+    #[arg(long)]
+    external_web_url: Option<String>,
+    /// Explicit launch mode, overriding the compile-time default: one of
+    /// `embedded-server`, `external-server`, `no-server`.
+    #[arg(long)]
+    server_mode: Option<String>,
+}
+
+/// On-disk config (`dipeo.toml` or `dipeo.json` in the resource dir), every
+/// field optional so CLI args and defaults can fill in the rest.
+#[derive(serde::Deserialize, Default)]
+struct FileConfig {
+    web_port: Option<u16>,
+    backend_port: Option<u16>,
+    bind: Option<String>,
+    no_backend: Option<bool>,
+    external_backend: Option<String>,
+    external_web_url: Option<String>,
+    server_mode: Option<String>,
+}
+
+/// Fully resolved runtime configuration: defaults, overridden by the config
+/// file, overridden by CLI args. Managed as Tauri state so every command can
+/// read it instead of relying on hardcoded ports.
+#[derive(Clone, Debug)]
+struct AppConfig {
+    web_port: u16,
+    backend_port: u16,
+    bind: String,
+    no_backend: bool,
+    external_backend: Option<String>,
+    external_web_url: Option<String>,
+    mode: ServerMode,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            web_port: 8871,
+            backend_port: 8885,
+            bind: "127.0.0.1".to_string(),
+            no_backend: false,
+            external_backend: None,
+            external_web_url: None,
+            mode: ServerMode::compile_time_default(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Merges (in increasing priority) defaults, an on-disk `FileConfig`,
+    /// and parsed CLI args.
+    fn resolve(file: FileConfig, cli: CliArgs) -> Self {
+        let defaults = Self::default();
+        let no_backend = cli.no_backend || file.no_backend.unwrap_or(false);
+        let external_backend = cli.external_backend.or(file.external_backend);
+
+        let mode = cli
+            .server_mode
+            .as_deref()
+            .or(file.server_mode.as_deref())
+            .and_then(ServerMode::parse)
+            .unwrap_or_else(|| {
+                if let Some(raw) = cli.server_mode.as_deref().or(file.server_mode.as_deref()) {
+                    log::warn!("Unknown server mode '{}', inferring the mode from the other flags", raw);
+                }
+                if no_backend {
+                    ServerMode::NoServer
+                } else if external_backend.is_some() {
+                    ServerMode::External
+                } else {
+                    ServerMode::compile_time_default()
+                }
+            });
+
+        Self {
+            web_port: cli.web_port.or(file.web_port).unwrap_or(defaults.web_port),
+            backend_port: cli.backend_port.or(file.backend_port).unwrap_or(defaults.backend_port),
+            bind: cli.bind.or(file.bind).unwrap_or(defaults.bind),
+            no_backend,
+            external_backend,
+            external_web_url: cli.external_web_url.or(file.external_web_url),
+            mode,
+        }
+    }
+
+    /// `backend_port` is the effective port to use, which may differ from
+    /// `self.backend_port` if that one was taken and `find_free_port` (see
+    /// `start_backend`) picked a different one.
+    fn backend_url(&self, backend_port: u16) -> String {
+        self.external_backend
+            .clone()
+            .unwrap_or_else(|| format!("http://{}:{}/graphql", self.bind, backend_port))
+    }
+}
+
+/// Reads `dipeo.toml` or `dipeo.json` from the resource dir, if present.
+/// Missing or unreadable config is not an error - it just means defaults
+/// and CLI args apply.
+fn load_file_config(app_handle: &AppHandle) -> FileConfig {
+    let Ok(resource_dir) = app_handle.path().resource_dir() else {
+        return FileConfig::default();
+    };
+
+    if let Ok(contents) = std::fs::read_to_string(resource_dir.join("dipeo.toml")) {
+        if let Ok(config) = toml::from_str(&contents) {
+            return config;
+        }
+        log::warn!("Failed to parse dipeo.toml, ignoring it");
+    }
+
+    if let Ok(contents) = std::fs::read_to_string(resource_dir.join("dipeo.json")) {
+        if let Ok(config) = serde_json::from_str(&contents) {
+            return config;
+        }
+        log::warn!("Failed to parse dipeo.json, falling back to defaults");
+    }
+
+    FileConfig::default()
+}
+
+/// Binds `preferred` on `bind` if free; otherwise scans upward for the next
+/// available port. Returns the port together with the `TcpListener` still
+/// holding it, so the caller can hand that listener straight to whatever
+/// actually serves on it (see `start_web_server`) instead of dropping it and
+/// re-binding later - a gap another process could win in between. Callers
+/// that can't reuse the listener directly (e.g. `start_backend`, which
+/// hands the port to a spawned child) should hold it for as long as
+/// possible and drop it immediately before the real bind happens, to keep
+/// that unavoidable window as narrow as possible.
+fn find_free_port(bind: &str, preferred: u16) -> (u16, TcpListener) {
+    if let Ok(listener) = TcpListener::bind((bind, preferred)) {
+        return (preferred, listener);
+    }
+
+    for port in preferred.saturating_add(1)..=preferred.saturating_add(100) {
+        if let Ok(listener) = TcpListener::bind((bind, port)) {
+            log::warn!("Port {} in use, falling back to {}", preferred, port);
+            return (port, listener);
+        }
+    }
+
+    log::warn!("No free port found near {}, binding it anyway", preferred);
+    match TcpListener::bind((bind, preferred)) {
+        Ok(listener) => (preferred, listener),
+        Err(e) => {
+            log::error!("Failed to bind fallback port {}: {}", preferred, e);
+            // Binding is about to fail again downstream anyway; hand back
+            // a listener bound to an OS-assigned ephemeral port so callers
+            // at least get something usable rather than panicking here.
+            let listener = TcpListener::bind((bind, 0)).expect("failed to bind any port");
+            let port = listener.local_addr().map(|a| a.port()).unwrap_or(preferred);
+            (port, listener)
+        }
+    }
+}
+
 struct BackendProcess(Mutex<Option<Child>>);
+/// When the current backend child was spawned, used to compute `uptime_secs`.
+struct BackendStarted(Mutex<Option<std::time::Instant>>);
+/// The backend port actually bound by the most recent `start_backend` call,
+/// which may differ from `AppConfig::backend_port` if that port was taken
+/// and `find_free_port` picked a different one - mirrors how the web
+/// server's bound port is resolved (see `WebServerUrl`). Seeded from
+/// `AppConfig::backend_port` at startup so health checks have a sane value
+/// before the backend has ever been started.
+struct BackendPort(Mutex<u16>);
+
+/// Set by `stop_backend` so a crash-restart that's already sleeping out its
+/// backoff (see `spawn_backend_supervisor`) knows not to bring the backend
+/// back up once the sleep ends. `start_backend` clears it again, since
+/// reaching it always means a restart was explicitly wanted.
+struct BackendStopRequested(std::sync::atomic::AtomicBool);
+
+/// Caps how many times the supervisor will auto-restart a crashed backend
+/// before giving up.
+const MAX_BACKEND_RESTARTS: u32 = 5;
+
+#[derive(Default)]
+struct BackendSupervisorStats {
+    restart_count: u32,
+    last_exit_code: Option<i32>,
+}
+
+/// Tracks crash/restart history. `restart_count` counts crashes since the
+/// backend was last reachable: the supervisor resets it to 0 whenever the
+/// backend comes back up.
+struct BackendStats(Mutex<BackendSupervisorStats>);
+
+#[derive(Clone, serde::Serialize)]
+struct BackendCrashEvent {
+    exit_code: Option<i32>,
+    restart_count: u32,
+}
+
+#[cfg(feature = "actix-web-server")]
 struct WebServerHandle(Mutex<Option<actix_web::dev::ServerHandle>>);
+#[cfg(not(feature = "actix-web-server"))]
+struct WebServerHandle(Mutex<Option<()>>);
+
+/// Publishes the URL `start_web_server` actually bound, which may differ
+/// from `AppConfig::web_port` if that port was taken and `find_free_port`
+/// picked a different one. The `setup` navigation logic waits on this
+/// instead of recomputing the URL from the pre-resolution config, so it
+/// never points the webview at a port nothing is listening on. Always
+/// managed (like `ControlToken`) so `start_web_server`'s signature doesn't
+/// need to vary by feature, though only the actix build ever sends on it.
+struct WebServerUrl(tokio::sync::watch::Sender<Option<String>>);
+
+/// The per-launch control-API token, generated once in `main` and handed to
+/// each control request handler via `TauriAppState`. Only meaningful (and
+/// only checked) when the actix control API is compiled in, but always
+/// managed so `start_web_server`'s signature doesn't need to vary by feature.
+struct ControlToken(String);
+
+/// Shared app handle injected into the actix server so control routes can
+/// drive the Tauri window and backend process.
+#[cfg(feature = "actix-web-server")]
+#[derive(Clone)]
+struct TauriAppState {
+    app_handle: AppHandle,
+    control_token: String,
+}
+
+/// Header carrying the per-launch control-API token (see `write_control_token`).
+#[cfg(feature = "actix-web-server")]
+const CONTROL_TOKEN_HEADER: &str = "x-dipeo-control-token";
+
+/// Generates a random per-launch token and writes it to `control.token` in
+/// the app's local data dir (mode 0600 on unix), so only something with
+/// filesystem access to the user's profile - the real desktop app, or
+/// CLI/CI tooling run by the same user - can read it and authenticate
+/// against the control API.
+#[cfg(feature = "actix-web-server")]
+fn write_control_token(app_handle: &AppHandle) -> Result<String, String> {
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token = bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+
+    let dir = app_handle
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let token_path = dir.join("control.token");
+    std::fs::write(&token_path, &token).map_err(|e| e.to_string())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = std::fs::metadata(&token_path) {
+            let mut perms = metadata.permissions();
+            perms.set_mode(0o600);
+            let _ = std::fs::set_permissions(&token_path, perms);
+        }
+    }
+
+    Ok(token)
+}
+
+/// Constant-time byte comparison so token checks don't leak timing info
+/// about how much of the token matched.
+#[cfg(feature = "actix-web-server")]
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Rejects any request that isn't from loopback *and* carrying the correct
+/// per-launch control token. A loopback peer address alone is not a trust
+/// boundary: any page open in the user's regular browser can also reach
+/// `127.0.0.1` (classic "localhost CSRF"), so the token - readable only from
+/// the on-disk file `write_control_token` writes - is the actual auth check.
+#[cfg(feature = "actix-web-server")]
+fn require_control_access(req: &HttpRequest, expected_token: &str) -> Result<(), HttpResponse> {
+    let is_local = req
+        .peer_addr()
+        .map(|addr| addr.ip().is_loopback())
+        .unwrap_or(false);
+
+    if !is_local {
+        return Err(HttpResponse::Forbidden().body("control API is localhost-only"));
+    }
+
+    let provided = req
+        .headers()
+        .get(CONTROL_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+
+    if constant_time_eq(provided.as_bytes(), expected_token.as_bytes()) {
+        Ok(())
+    } else {
+        Err(HttpResponse::Unauthorized().body("missing or invalid control token"))
+    }
+}
+
+/// Abstraction over the single window op each `control_window_*` route
+/// needs, so `run_window_action` - the decision logic those routes delegate
+/// to - can be exercised against a fake window in tests instead of a real
+/// window system (see `window_action_tests`).
+#[cfg(feature = "actix-web-server")]
+trait WindowOps {
+    fn show(&self) -> Result<(), String>;
+    fn hide(&self) -> Result<(), String>;
+    fn focus(&self) -> Result<(), String>;
+}
+
+#[cfg(feature = "actix-web-server")]
+impl WindowOps for tauri::WebviewWindow {
+    fn show(&self) -> Result<(), String> {
+        tauri::WebviewWindow::show(self).map_err(|e| e.to_string())
+    }
+    fn hide(&self) -> Result<(), String> {
+        tauri::WebviewWindow::hide(self).map_err(|e| e.to_string())
+    }
+    fn focus(&self) -> Result<(), String> {
+        tauri::WebviewWindow::set_focus(self).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "actix-web-server")]
+enum WindowActionError {
+    NotFound,
+    Failed(String),
+}
+
+/// Applies `action` to `window` if present, distinguishing "no main window"
+/// from "the action itself failed" so callers can map each to the right
+/// HTTP status.
+#[cfg(feature = "actix-web-server")]
+fn run_window_action<W: WindowOps>(
+    window: Option<W>,
+    action: impl FnOnce(&W) -> Result<(), String>,
+) -> Result<(), WindowActionError> {
+    match window {
+        Some(w) => action(&w).map_err(WindowActionError::Failed),
+        None => Err(WindowActionError::NotFound),
+    }
+}
+
+#[cfg(feature = "actix-web-server")]
+fn window_action_response(result: Result<(), WindowActionError>) -> HttpResponse {
+    match result {
+        Ok(()) => HttpResponse::Ok().finish(),
+        Err(WindowActionError::NotFound) => HttpResponse::NotFound().body("main window not found"),
+        Err(WindowActionError::Failed(e)) => HttpResponse::InternalServerError().body(e),
+    }
+}
 
-#[derive(serde::Serialize)]
+#[cfg(feature = "actix-web-server")]
+async fn control_window_show(req: HttpRequest, state: web::Data<TauriAppState>) -> HttpResponse {
+    if let Err(resp) = require_control_access(&req, &state.control_token) {
+        return resp;
+    }
+    window_action_response(run_window_action(state.app_handle.get_webview_window("main"), |w| w.show()))
+}
+
+#[cfg(feature = "actix-web-server")]
+async fn control_window_hide(req: HttpRequest, state: web::Data<TauriAppState>) -> HttpResponse {
+    if let Err(resp) = require_control_access(&req, &state.control_token) {
+        return resp;
+    }
+    window_action_response(run_window_action(state.app_handle.get_webview_window("main"), |w| w.hide()))
+}
+
+#[cfg(feature = "actix-web-server")]
+async fn control_window_focus(req: HttpRequest, state: web::Data<TauriAppState>) -> HttpResponse {
+    if let Err(resp) = require_control_access(&req, &state.control_token) {
+        return resp;
+    }
+    window_action_response(run_window_action(state.app_handle.get_webview_window("main"), |w| w.focus()))
+}
+
+/// Abstraction over "stop the backend, then start it again", so
+/// `restart_backend` - the sequencing `control_backend_restart` delegates
+/// to - can be exercised against a fake in tests instead of spawning a real
+/// child process (see `backend_restart_tests`).
+#[cfg(feature = "actix-web-server")]
+trait BackendControl {
+    async fn stop(&self) -> Result<(), String>;
+    async fn start(&self) -> Result<(), String>;
+}
+
+#[cfg(feature = "actix-web-server")]
+struct RealBackendControl<'a> {
+    app_handle: &'a AppHandle,
+}
+
+#[cfg(feature = "actix-web-server")]
+impl BackendControl for RealBackendControl<'_> {
+    async fn stop(&self) -> Result<(), String> {
+        stop_backend(
+            self.app_handle.state::<BackendProcess>(),
+            self.app_handle.state::<BackendStarted>(),
+            self.app_handle.state::<AppConfig>(),
+            self.app_handle.state::<BackendPort>(),
+            self.app_handle.state::<BackendStopRequested>(),
+        )
+        .await
+    }
+
+    async fn start(&self) -> Result<(), String> {
+        start_backend(
+            self.app_handle.clone(),
+            self.app_handle.state::<BackendProcess>(),
+            self.app_handle.state::<AppConfig>(),
+            self.app_handle.state::<BackendStarted>(),
+            self.app_handle.state::<BackendPort>(),
+            self.app_handle.state::<BackendStopRequested>(),
+        )
+        .await
+    }
+}
+
+/// Stops then restarts the backend, turning either step's failure into the
+/// same message `control_backend_restart` reports.
+#[cfg(feature = "actix-web-server")]
+async fn restart_backend(control: &impl BackendControl) -> Result<(), String> {
+    control.stop().await.map_err(|e| format!("failed to stop backend: {e}"))?;
+    control.start().await.map_err(|e| format!("failed to start backend: {e}"))
+}
+
+#[cfg(feature = "actix-web-server")]
+async fn control_backend_restart(req: HttpRequest, state: web::Data<TauriAppState>) -> HttpResponse {
+    if let Err(resp) = require_control_access(&req, &state.control_token) {
+        return resp;
+    }
+    match restart_backend(&RealBackendControl { app_handle: &state.app_handle }).await {
+        Ok(()) => HttpResponse::Ok().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
 struct BackendStatus {
     running: bool,
     url: String,
+    pid: Option<u32>,
+    uptime_secs: u64,
+    last_error: Option<String>,
 }
 
-#[tauri::command]
-async fn check_backend_health() -> Result<BackendStatus, String> {
-    let backend_url = "http://localhost:8885/graphql";
-    
-    match reqwest::get(backend_url).await {
-        Ok(_) => Ok(BackendStatus {
-            running: true,
-            url: backend_url.to_string(),
-        }),
-        Err(_) => Ok(BackendStatus {
-            running: false,
-            url: backend_url.to_string(),
-        }),
+/// Probes `backend_url`, reporting `Err` with a human-readable reason on
+/// failure instead of just a bool, so callers can surface `last_error`.
+async fn probe_backend(backend_url: &str) -> Result<(), String> {
+    reqwest::get(backend_url)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Assembles a `BackendStatus` from the process/health state that's spread
+/// across managed state, used by both `check_backend_health` and the
+/// background supervisor so the two report the same shape.
+async fn build_backend_status(
+    backend_url: String,
+    backend_process: &State<'_, BackendProcess>,
+    started_at: &State<'_, BackendStarted>,
+) -> BackendStatus {
+    let last_error = probe_backend(&backend_url).await.err();
+    let running = last_error.is_none();
+
+    let pid = backend_process.0.lock().unwrap().as_ref().map(|c| c.id());
+    let uptime_secs = if running {
+        started_at
+            .0
+            .lock()
+            .unwrap()
+            .map(|t| t.elapsed().as_secs())
+            .unwrap_or(0)
+    } else {
+        0
+    };
+
+    BackendStatus {
+        running,
+        url: backend_url,
+        pid,
+        uptime_secs,
+        last_error,
     }
 }
 
+#[tauri::command]
+async fn check_backend_health(
+    config: State<'_, AppConfig>,
+    backend_process: State<'_, BackendProcess>,
+    started_at: State<'_, BackendStarted>,
+    backend_port: State<'_, BackendPort>,
+) -> Result<BackendStatus, String> {
+    let port = *backend_port.0.lock().unwrap();
+    Ok(build_backend_status(config.backend_url(port), &backend_process, &started_at).await)
+}
+
 #[tauri::command]
 async fn start_backend(
     app_handle: AppHandle,
     backend_process: State<'_, BackendProcess>,
+    config: State<'_, AppConfig>,
+    started_at: State<'_, BackendStarted>,
+    backend_port: State<'_, BackendPort>,
+    stop_requested: State<'_, BackendStopRequested>,
 ) -> Result<(), String> {
+    // Reaching this point always means a (re)start was explicitly wanted,
+    // so any pending crash-restart cancellation no longer applies.
+    stop_requested.0.store(false, std::sync::atomic::Ordering::SeqCst);
+
+    match config.mode {
+        ServerMode::NoServer => {
+            log::info!("Server mode is no-server; not spawning a backend");
+            return Ok(());
+        }
+        ServerMode::External => {
+            log::info!(
+                "Server mode is external; using backend at {}, not spawning one",
+                config.backend_url(config.backend_port)
+            );
+            return Ok(());
+        }
+        ServerMode::Embedded => {}
+    }
+
     // First check if backend is already running and get the child process if needed
     {
         let mut process_guard = backend_process.0.lock().unwrap();
-        
+
         // Check if backend is already running
         if process_guard.is_some() {
             return Ok(());
         }
-        
+
         // Get the installation directory
         let resource_path = app_handle
             .path()
             .resource_dir()
             .map_err(|e| e.to_string())?;
-        
+
         // In production, the backend exe is in the same directory as the main exe
         let backend_exe = if cfg!(debug_assertions) {
             // Development mode - use the Python script
@@ -62,13 +627,29 @@ async fn start_backend(
                 .ok_or("Failed to get parent directory")?
                 .join("dipeo-server.exe")
         };
-        
+
         log::info!("Starting backend from: {:?}", backend_exe);
-        
+
+        // Scan for a free port the same way the web server does, rather
+        // than handing the configured port straight to the child and
+        // letting it fail (or silently collide) if something else is
+        // already bound to it. Unlike the web server we can't hand the
+        // listener to the child directly - it binds the port itself in a
+        // separate process - so we hold it open as long as possible and
+        // only drop it immediately before spawning, to keep the window
+        // another process could steal the port in as narrow as possible.
+        let (port, listener) = find_free_port(&config.bind, config.backend_port);
+        if port != config.backend_port {
+            log::warn!("Requested backend port {} was taken, using {} instead", config.backend_port, port);
+        }
+
         let child = if cfg!(debug_assertions) {
             // Development mode - run with Python
+            drop(listener);
             Command::new("python")
                 .arg(&backend_exe)
+                .arg("--port")
+                .arg(port.to_string())
                 .spawn()
                 .map_err(|e| format!("Failed to start backend: {}", e))?
         } else {
@@ -76,59 +657,112 @@ async fn start_backend(
             if !backend_exe.exists() {
                 return Err(format!("Backend executable not found at: {:?}", backend_exe));
             }
-            
+
+            drop(listener);
             Command::new(&backend_exe)
+                .arg("--port")
+                .arg(port.to_string())
                 .spawn()
                 .map_err(|e| format!("Failed to start backend: {}", e))?
         };
-        
+
+        *backend_port.0.lock().unwrap() = port;
         *process_guard = Some(child);
+        *started_at.0.lock().unwrap() = Some(std::time::Instant::now());
     } // Lock is dropped here
-    
-    // Wait for backend to be ready
-    for i in 0..30 {
-        sleep(Duration::from_millis(500)).await;
-        if let Ok(status) = check_backend_health().await {
-            if status.running {
-                log::info!("Backend started successfully after {} attempts", i + 1);
-                return Ok(());
-            }
-        }
-    }
-    
-    Err("Backend failed to start within 15 seconds".to_string())
+
+    // Readiness is no longer polled here - the background supervisor task
+    // (see `spawn_backend_supervisor`) probes the backend on an interval and
+    // emits `backend-status` events, which callers should listen for instead
+    // of blocking on this command.
+    log::info!("Backend process spawned, awaiting readiness via backend-status events");
+    Ok(())
 }
 
-#[tauri::command]
-async fn stop_backend(backend_process: State<'_, BackendProcess>) -> Result<(), String> {
-    let mut process_guard = backend_process.0.lock().unwrap();
-    
-    if let Some(mut child) = process_guard.take() {
-        child.kill().map_err(|e| e.to_string())?;
-        log::info!("Backend stopped");
+/// Sends `SIGTERM` on Unix as a second, more forceful nudge before the
+/// final `kill()`. Windows has no equivalent lightweight signal, so the
+/// graceful attempt there is limited to the `/shutdown` HTTP call.
+#[cfg(unix)]
+fn send_graceful_signal(pid: u32) {
+    unsafe {
+        libc::kill(pid as i32, libc::SIGTERM);
     }
-    
-    Ok(())
 }
+#[cfg(not(unix))]
+fn send_graceful_signal(_pid: u32) {}
 
 #[tauri::command]
-async fn start_web_server(
-    app_handle: AppHandle,
-    web_server_handle: State<'_, WebServerHandle>,
+async fn stop_backend(
+    backend_process: State<'_, BackendProcess>,
+    started_at: State<'_, BackendStarted>,
+    config: State<'_, AppConfig>,
+    backend_port: State<'_, BackendPort>,
+    stop_requested: State<'_, BackendStopRequested>,
 ) -> Result<(), String> {
-    let mut server_guard = web_server_handle.0.lock().unwrap();
-    
-    // Check if server is already running
-    if server_guard.is_some() {
+    // Cancel any crash-restart that's currently sleeping out its backoff -
+    // without this, a stop called while the supervisor is between a crash
+    // and its scheduled restart silently no-ops here and the backend comes
+    // back up right after, as if the stop had never happened.
+    stop_requested.0.store(true, std::sync::atomic::Ordering::SeqCst);
+
+    let child = {
+        let mut process_guard = backend_process.0.lock().unwrap();
+        process_guard.take()
+    };
+
+    let Some(mut child) = child else {
         return Ok(());
+    };
+
+    let pid = child.id();
+    log::info!("Stopping backend (pid {})", pid);
+
+    // 1. Ask nicely: the GraphQL server can flush and exit on its own.
+    let port = *backend_port.0.lock().unwrap();
+    let shutdown_url = format!("http://{}:{}/shutdown", config.bind, port);
+    let _ = reqwest::Client::new()
+        .post(&shutdown_url)
+        .timeout(Duration::from_secs(2))
+        .send()
+        .await;
+
+    // 2. Platform-appropriate signal as a second nudge.
+    send_graceful_signal(pid);
+
+    // 3. Give it a few seconds to exit on its own before force-killing.
+    let exited_gracefully = async {
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) => return true,
+                Ok(None) => sleep(Duration::from_millis(200)).await,
+                Err(_) => return false,
+            }
+        }
+    };
+    if !tokio::time::timeout(Duration::from_secs(5), exited_gracefully)
+        .await
+        .unwrap_or(false)
+    {
+        log::warn!("Backend did not exit gracefully within 5s, killing it");
+        child.kill().map_err(|e| e.to_string())?;
+        let _ = child.wait();
     }
-    
-    // Get the web directory
+
+    log::info!("Backend stopped");
+    *started_at.0.lock().unwrap() = None;
+
+    Ok(())
+}
+
+/// Resolves the directory the built web assets live in, for both the
+/// (feature-gated) actix static file server and the `app://` protocol
+/// handler that serves them by default.
+fn resolve_web_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
     let resource_path = app_handle
         .path()
         .resource_dir()
         .map_err(|e| e.to_string())?;
-    
+
     let web_dir = if cfg!(debug_assertions) {
         // Development mode - use the built web files
         resource_path.join("..").join("..").join("..").join("apps").join("web").join("dist")
@@ -138,37 +772,167 @@ async fn start_web_server(
             .ok_or("Failed to get parent directory")?
             .join("web")
     };
-    
+
     if !web_dir.exists() {
         return Err(format!("Web directory not found at: {:?}", web_dir));
     }
-    
+
+    Ok(web_dir)
+}
+
+/// True if `candidate` canonicalizes to a path inside (or equal to) `root`.
+/// Catches both `..` traversal and an absolute/drive-rooted request path
+/// (e.g. `C:\Windows\win.ini` on Windows), either of which makes
+/// `PathBuf::join` silently discard `root` and resolve outside it -
+/// `is_file()` on the raw joined path doesn't protect against that.
+fn path_is_within(root: &Path, candidate: &Path) -> bool {
+    let (Ok(root), Ok(candidate)) = (root.canonicalize(), candidate.canonicalize()) else {
+        return false;
+    };
+    candidate.starts_with(root)
+}
+
+/// Resolves a `tauri::http::Request` against `web_dir`, falling back to
+/// `index.html` for unknown paths (SPA-style routing), and reads the
+/// matching file's bytes with a best-effort content type.
+fn serve_web_asset(web_dir: &Path, request: &tauri::http::Request<Vec<u8>>) -> tauri::http::Response<Vec<u8>> {
+    let url_path = request.uri().path().trim_start_matches('/');
+    let candidate = if url_path.is_empty() {
+        None
+    } else {
+        Some(web_dir.join(url_path))
+    };
+
+    let file_path = candidate
+        .filter(|path| path_is_within(web_dir, path))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| web_dir.join("index.html"));
+
+    match std::fs::read(&file_path) {
+        Ok(bytes) => {
+            let mime = mime_guess::from_path(&file_path).first_or_octet_stream();
+            tauri::http::Response::builder()
+                .header("Content-Type", mime.as_ref())
+                .body(bytes)
+                .unwrap_or_else(|_| {
+                    tauri::http::Response::builder().status(500).body(Vec::new()).unwrap()
+                })
+        }
+        Err(e) => {
+            log::error!("Failed to read asset {:?}: {}", file_path, e);
+            tauri::http::Response::builder().status(404).body(Vec::new()).unwrap()
+        }
+    }
+}
+
+#[cfg(feature = "actix-web-server")]
+#[tauri::command]
+async fn start_web_server(
+    app_handle: AppHandle,
+    web_server_handle: State<'_, WebServerHandle>,
+    config: State<'_, AppConfig>,
+    control_token: State<'_, ControlToken>,
+    web_server_url: State<'_, WebServerUrl>,
+) -> Result<(), String> {
+    if config.mode != ServerMode::Embedded {
+        log::info!("Server mode {:?} does not run the bundled web server", config.mode);
+        return Ok(());
+    }
+
+    let mut server_guard = web_server_handle.0.lock().unwrap();
+
+    // Check if server is already running
+    if server_guard.is_some() {
+        return Ok(());
+    }
+
+    let web_dir = resolve_web_dir(&app_handle)?;
+
     log::info!("Serving web files from: {:?}", web_dir);
-    
-    // Start the web server
+
+    let control_state = TauriAppState {
+        app_handle: app_handle.clone(),
+        control_token: control_token.0.clone(),
+    };
+
+    let bind = config.bind.clone();
+    let (port, listener) = find_free_port(&bind, config.web_port);
+    if port != config.web_port {
+        log::warn!("Requested web port {} was taken, using {} instead", config.web_port, port);
+    }
+
+    // Hand the already-bound listener straight to actix instead of binding
+    // a fresh socket on `port` - that would reopen the exact race
+    // `find_free_port` exists to avoid, since another process could grab
+    // the port in between.
     let server = HttpServer::new(move || {
         App::new()
+            .app_data(web::Data::new(control_state.clone()))
+            .service(
+                web::scope("/control")
+                    .route("/window/show", web::post().to(control_window_show))
+                    .route("/window/hide", web::post().to(control_window_hide))
+                    .route("/window/focus", web::post().to(control_window_focus))
+                    .route("/backend/restart", web::post().to(control_backend_restart)),
+            )
             .service(fs::Files::new("/", web_dir.clone())
                 .index_file("index.html")
                 .show_files_listing())
     })
-    .bind(("127.0.0.1", 8871))
+    .listen(listener)
     .map_err(|e| format!("Failed to bind web server: {}", e))?
     .run();
-    
+
     let handle = server.handle();
     *server_guard = Some(handle);
     drop(server_guard); // Release the lock
-    
+
     // Spawn the server in a separate task
     tauri::async_runtime::spawn(async move {
         let _ = server.await;
     });
-    
-    log::info!("Web server started on http://localhost:8871");
+
+    log::info!("Web server started on http://{}:{}", bind, port);
+    let _ = web_server_url.0.send(Some(format!("http://{}:{}", bind, port)));
     Ok(())
 }
 
+/// Static assets are served through the `app://` custom protocol by
+/// default (see `register_uri_scheme_protocol` in `main`), so there is no
+/// localhost socket to bind here.
+#[cfg(not(feature = "actix-web-server"))]
+#[tauri::command]
+async fn start_web_server(
+    _app_handle: AppHandle,
+    _web_server_handle: State<'_, WebServerHandle>,
+    _config: State<'_, AppConfig>,
+    _control_token: State<'_, ControlToken>,
+    _web_server_url: State<'_, WebServerUrl>,
+) -> Result<(), String> {
+    Ok(())
+}
+
+/// Generates and manages the per-launch `ControlToken`. Under the actix
+/// feature this is the real shared secret the control API checks; without
+/// it, there's no control API to protect, so a placeholder keeps the
+/// managed-state shape (and `start_web_server`'s signature) the same across
+/// both builds.
+#[cfg(feature = "actix-web-server")]
+fn manage_control_token(app: &tauri::App) {
+    let token = write_control_token(app.handle()).unwrap_or_else(|e| {
+        log::error!("Failed to persist control token, using an in-memory-only one: {}", e);
+        let mut bytes = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut bytes);
+        bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+    });
+    app.manage(ControlToken(token));
+}
+#[cfg(not(feature = "actix-web-server"))]
+fn manage_control_token(app: &tauri::App) {
+    app.manage(ControlToken(String::new()));
+}
+
+#[cfg(feature = "actix-web-server")]
 #[tauri::command]
 async fn stop_web_server(web_server_handle: State<'_, WebServerHandle>) -> Result<(), String> {
     // Take the handle out of the mutex to avoid holding the lock across await
@@ -176,18 +940,147 @@ async fn stop_web_server(web_server_handle: State<'_, WebServerHandle>) -> Resul
         let mut server_guard = web_server_handle.0.lock().unwrap();
         server_guard.take()
     }; // Lock is dropped here
-    
+
     if let Some(handle) = handle {
         handle.stop(true).await;
         log::info!("Web server stopped");
     }
-    
+
     Ok(())
 }
 
+#[cfg(not(feature = "actix-web-server"))]
+#[tauri::command]
+async fn stop_web_server(_web_server_handle: State<'_, WebServerHandle>) -> Result<(), String> {
+    Ok(())
+}
+
+/// Probes the backend on an interval and emits a `backend-status` event to
+/// all windows whenever the reported status changes, so the frontend can
+/// render a live connection indicator instead of guessing from a timeout.
+/// Returns a `watch` receiver callers can use to wait for the first
+/// `running: true` status (see the `setup` navigation logic in `main`).
+fn spawn_backend_supervisor(app_handle: AppHandle) -> tokio::sync::watch::Receiver<BackendStatus> {
+    let initial = BackendStatus {
+        running: false,
+        url: app_handle
+            .state::<AppConfig>()
+            .backend_url(*app_handle.state::<BackendPort>().0.lock().unwrap()),
+        pid: None,
+        uptime_secs: 0,
+        last_error: None,
+    };
+    let (tx, rx) = tokio::sync::watch::channel(initial);
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let config = app_handle.state::<AppConfig>();
+            let backend_process = app_handle.state::<BackendProcess>();
+            let started_at = app_handle.state::<BackendStarted>();
+            let backend_port = app_handle.state::<BackendPort>();
+
+            // Detect a crash: the child exited without going through
+            // `stop_backend` (which always clears `backend_process` first).
+            let exit_status = {
+                let mut guard = backend_process.0.lock().unwrap();
+                match guard.as_mut() {
+                    Some(child) => child.try_wait().ok().flatten(),
+                    None => None,
+                }
+            };
+
+            if let Some(status) = exit_status {
+                *backend_process.0.lock().unwrap() = None;
+                *started_at.0.lock().unwrap() = None;
+
+                let exit_code = status.code();
+                let restart_count = {
+                    let mut stats = app_handle.state::<BackendStats>().0.lock().unwrap();
+                    stats.last_exit_code = exit_code;
+                    stats.restart_count += 1;
+                    stats.restart_count
+                };
+
+                log::error!("Backend crashed (exit code {:?}), restart #{}", exit_code, restart_count);
+                let _ = app_handle.emit_all(
+                    "backend-crashed",
+                    BackendCrashEvent { exit_code, restart_count },
+                );
+
+                let can_restart = restart_count <= MAX_BACKEND_RESTARTS
+                    && !config.no_backend
+                    && config.external_backend.is_none();
+
+                if can_restart {
+                    let backoff_secs = 2u64.saturating_pow(restart_count.min(6)).min(60);
+                    log::warn!(
+                        "Restarting backend in {}s (attempt {}/{})",
+                        backoff_secs,
+                        restart_count,
+                        MAX_BACKEND_RESTARTS
+                    );
+                    sleep(Duration::from_secs(backoff_secs)).await;
+
+                    let stop_requested = app_handle.state::<BackendStopRequested>();
+                    if stop_requested.0.load(std::sync::atomic::Ordering::SeqCst) {
+                        log::info!("Backend was explicitly stopped during backoff; skipping auto-restart");
+                    } else if let Err(e) = start_backend(
+                        app_handle.clone(),
+                        backend_process,
+                        config,
+                        started_at,
+                        backend_port,
+                        stop_requested,
+                    )
+                    .await
+                    {
+                        log::error!("Auto-restart failed: {}", e);
+                    }
+                } else {
+                    log::error!("Backend crashed {} times, giving up auto-restart", restart_count);
+                }
+
+                continue;
+            }
+
+            let status = build_backend_status(
+                config.backend_url(*backend_port.0.lock().unwrap()),
+                &backend_process,
+                &started_at,
+            )
+            .await;
+
+            let changed = {
+                let current = tx.borrow();
+                current.running != status.running
+                    || current.pid != status.pid
+                    || current.last_error != status.last_error
+            };
+
+            if changed {
+                if status.running {
+                    log::info!("Backend is now reachable at {}", status.url);
+                    app_handle.state::<BackendStats>().0.lock().unwrap().restart_count = 0;
+                } else if let Some(err) = &status.last_error {
+                    log::warn!("Backend unreachable: {}", err);
+                }
+                let _ = app_handle.emit_all("backend-status", status.clone());
+                let _ = tx.send(status);
+            }
+
+            sleep(Duration::from_secs(2)).await;
+        }
+    });
+
+    rx
+}
+
 fn main() {
     env_logger::init();
-    
+
+    let cli = CliArgs::parse();
+    let (web_server_url_tx, web_server_url_rx) = tokio::sync::watch::channel(None::<String>);
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_process::init())
@@ -195,8 +1088,21 @@ fn main() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_http::init())
+        .register_uri_scheme_protocol("app", |app_handle, request| {
+            match resolve_web_dir(app_handle) {
+                Ok(web_dir) => serve_web_asset(&web_dir, request),
+                Err(e) => {
+                    log::error!("Failed to resolve web dir for app:// protocol: {}", e);
+                    tauri::http::Response::builder().status(500).body(Vec::new()).unwrap()
+                }
+            }
+        })
         .manage(BackendProcess(Mutex::new(None)))
+        .manage(BackendStarted(Mutex::new(None)))
+        .manage(BackendStopRequested(std::sync::atomic::AtomicBool::new(false)))
+        .manage(BackendStats(Mutex::new(BackendSupervisorStats::default())))
         .manage(WebServerHandle(Mutex::new(None)))
+        .manage(WebServerUrl(web_server_url_tx))
         .invoke_handler(tauri::generate_handler![
             check_backend_health,
             start_backend,
@@ -204,60 +1110,539 @@ fn main() {
             start_web_server,
             stop_web_server
         ])
-        .setup(|app| {
+        .setup(move |app| {
+            let file_config = load_file_config(app.handle());
+            let config = AppConfig::resolve(file_config, cli);
+            log::info!("Resolved config: {:?}", config);
+
+            let mut web_server_url_rx = web_server_url_rx;
+            let mode = config.mode;
+            let external_web_url = config.external_web_url.clone();
+            app.manage(BackendPort(Mutex::new(config.backend_port)));
+            app.manage(config);
+            manage_control_token(app);
+
             // Clone handles for async block before moving into spawn
             let app_handle_backend = app.handle().clone();
             let app_handle_web = app.handle().clone();
-            
+
+            let mut backend_status_rx = spawn_backend_supervisor(app.handle().clone());
+
             // Start servers in background
             tauri::async_runtime::spawn(async move {
                 log::info!("Starting services...");
-                
+
                 // Get state inside the async block
                 let backend_state = app_handle_backend.state::<BackendProcess>();
                 let web_server_state = app_handle_web.state::<WebServerHandle>();
-                
+
                 // Start backend first
-                if let Err(e) = start_backend(app_handle_backend.clone(), backend_state).await {
+                if let Err(e) = start_backend(
+                    app_handle_backend.clone(),
+                    backend_state,
+                    app_handle_backend.state::<AppConfig>(),
+                    app_handle_backend.state::<BackendStarted>(),
+                    app_handle_backend.state::<BackendPort>(),
+                    app_handle_backend.state::<BackendStopRequested>(),
+                )
+                .await
+                {
                     log::error!("Failed to start backend: {}", e);
                 } else {
                     log::info!("Backend started successfully");
                 }
-                
-                // Then start web server
-                if let Err(e) = start_web_server(app_handle_web.clone(), web_server_state).await {
+
+                // Then start web server (feature-gated actix path only; the
+                // `app://` protocol serves assets by default, see above)
+                if let Err(e) = start_web_server(
+                    app_handle_web.clone(),
+                    web_server_state,
+                    app_handle_web.state::<AppConfig>(),
+                    app_handle_web.state::<ControlToken>(),
+                    app_handle_web.state::<WebServerUrl>(),
+                )
+                .await
+                {
                     log::error!("Failed to start web server: {}", e);
                 } else {
                     log::info!("Web server started successfully");
                 }
             });
-            
-            // Set the window to load from our local server
+
+            // Point the window at whichever asset source is active, but only
+            // once the supervisor reports the backend as running (or a
+            // timeout elapses, so a dead backend doesn't strand the UI). In
+            // no-server mode there's no backend to wait for at all.
             if let Some(window) = app.get_webview_window("main") {
                 tauri::async_runtime::spawn(async move {
-                    // Wait a bit for servers to start
-                    sleep(Duration::from_secs(3)).await;
-                    let _ = window.eval("window.location.href = 'http://localhost:8871'");
+                    if mode == ServerMode::NoServer {
+                        log::info!("Server mode is no-server; skipping backend readiness wait");
+                    } else {
+                        let wait_for_backend = async {
+                            while backend_status_rx.changed().await.is_ok() {
+                                if backend_status_rx.borrow().running {
+                                    return;
+                                }
+                            }
+                        };
+
+                        match tokio::time::timeout(Duration::from_secs(15), wait_for_backend).await {
+                            Ok(()) => log::info!("Backend ready, revealing UI"),
+                            Err(_) => log::warn!("Backend readiness timed out after 15s, revealing UI anyway"),
+                        }
+                    }
+
+                    // Assets are always reachable through the `app://`
+                    // protocol handler (registered unconditionally in
+                    // `main`, regardless of feature or server mode), so
+                    // it's the only URL safe to fall back to when there's
+                    // no locally-served UI to point at instead.
+                    let static_asset_url = "app://localhost/index.html".to_string();
+
+                    let target_url = match mode {
+                        ServerMode::External => external_web_url.unwrap_or_else(|| {
+                            log::warn!(
+                                "Server mode is external but no external_web_url was configured; \
+                                 falling back to the bundled UI"
+                            );
+                            static_asset_url.clone()
+                        }),
+                        ServerMode::NoServer => static_asset_url,
+                        ServerMode::Embedded => {
+                            #[cfg(feature = "actix-web-server")]
+                            {
+                                // Wait for `start_web_server` to publish the
+                                // URL it actually bound, which may differ
+                                // from `AppConfig::web_port` if that port
+                                // was taken (see `find_free_port`).
+                                let wait_for_bound_url = async {
+                                    loop {
+                                        if let Some(url) = web_server_url_rx.borrow().clone() {
+                                            return url;
+                                        }
+                                        if web_server_url_rx.changed().await.is_err() {
+                                            return static_asset_url.clone();
+                                        }
+                                    }
+                                };
+                                tokio::time::timeout(Duration::from_secs(10), wait_for_bound_url)
+                                    .await
+                                    .unwrap_or_else(|_| {
+                                        log::warn!("Web server did not report its bound URL in time");
+                                        static_asset_url.clone()
+                                    })
+                            }
+                            #[cfg(not(feature = "actix-web-server"))]
+                            {
+                                static_asset_url
+                            }
+                        }
+                    };
+
+                    match target_url.parse() {
+                        Ok(url) => {
+                            if let Err(e) = window.navigate(url) {
+                                log::error!("Failed to navigate to {}: {}", target_url, e);
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("Resolved target URL {} is not valid: {}", target_url, e);
+                        }
+                    }
                 });
             }
-            
+
             Ok(())
         })
         .on_window_event(|window, event| {
-            if let WindowEvent::CloseRequested { .. } = event {
-                // Stop both processes when window closes
-                let backend_process = window.state::<BackendProcess>();
-                let web_server_handle = window.state::<WebServerHandle>();
-                
-                if let Err(e) = tauri::async_runtime::block_on(stop_backend(backend_process)) {
-                    log::error!("Failed to stop backend: {}", e);
-                }
-                
-                if let Err(e) = tauri::async_runtime::block_on(stop_web_server(web_server_handle)) {
-                    log::error!("Failed to stop web server: {}", e);
-                }
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                // Graceful shutdown takes a few seconds; defer the actual
+                // close until it's done instead of blocking this (sync)
+                // callback on the async runtime.
+                api.prevent_close();
+                let window = window.clone();
+
+                tauri::async_runtime::spawn(async move {
+                    let backend_process = window.state::<BackendProcess>();
+                    let backend_started = window.state::<BackendStarted>();
+                    let config = window.state::<AppConfig>();
+                    let backend_port = window.state::<BackendPort>();
+                    let web_server_handle = window.state::<WebServerHandle>();
+                    let stop_requested = window.state::<BackendStopRequested>();
+
+                    if let Err(e) = stop_backend(backend_process, backend_started, config, backend_port, stop_requested).await {
+                        log::error!("Failed to stop backend: {}", e);
+                    }
+
+                    if let Err(e) = stop_web_server(web_server_handle).await {
+                        log::error!("Failed to stop web server: {}", e);
+                    }
+
+                    // `close()` would emit `CloseRequested` again and land
+                    // back in this handler; `destroy()` closes without it.
+                    let _ = window.destroy();
+                });
             }
         })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
+}
+
+#[cfg(all(test, feature = "actix-web-server"))]
+mod tests {
+    use super::*;
+    use actix_web::http::header::HeaderValue;
+    use actix_web::test::TestRequest;
+
+    const TOKEN: &str = "the-real-token";
+
+    #[test]
+    fn require_control_access_allows_loopback_peer_with_correct_token() {
+        let req = TestRequest::default()
+            .peer_addr("127.0.0.1:54321".parse().unwrap())
+            .insert_header((CONTROL_TOKEN_HEADER, HeaderValue::from_static(TOKEN)))
+            .to_http_request();
+
+        assert!(require_control_access(&req, TOKEN).is_ok());
+    }
+
+    #[test]
+    fn require_control_access_rejects_remote_peer_even_with_correct_token() {
+        let req = TestRequest::default()
+            .peer_addr("203.0.113.5:54321".parse().unwrap())
+            .insert_header((CONTROL_TOKEN_HEADER, HeaderValue::from_static(TOKEN)))
+            .to_http_request();
+
+        assert!(require_control_access(&req, TOKEN).is_err());
+    }
+
+    #[test]
+    fn require_control_access_rejects_missing_peer_addr() {
+        let req = TestRequest::default()
+            .insert_header((CONTROL_TOKEN_HEADER, HeaderValue::from_static(TOKEN)))
+            .to_http_request();
+
+        assert!(require_control_access(&req, TOKEN).is_err());
+    }
+
+    #[test]
+    fn require_control_access_rejects_loopback_peer_without_token() {
+        let req = TestRequest::default()
+            .peer_addr("127.0.0.1:54321".parse().unwrap())
+            .to_http_request();
+
+        assert!(require_control_access(&req, TOKEN).is_err());
+    }
+
+    #[test]
+    fn require_control_access_rejects_loopback_peer_with_wrong_token() {
+        let req = TestRequest::default()
+            .peer_addr("127.0.0.1:54321".parse().unwrap())
+            .insert_header((CONTROL_TOKEN_HEADER, HeaderValue::from_static("not-the-token")))
+            .to_http_request();
+
+        assert!(require_control_access(&req, TOKEN).is_err());
+    }
+
+    #[test]
+    fn constant_time_eq_matches_standard_equality() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+}
+
+#[cfg(all(test, feature = "actix-web-server"))]
+mod window_action_tests {
+    use super::*;
+
+    struct FakeWindow {
+        show_result: Result<(), String>,
+        hide_result: Result<(), String>,
+        focus_result: Result<(), String>,
+    }
+
+    impl Default for FakeWindow {
+        fn default() -> Self {
+            Self {
+                show_result: Ok(()),
+                hide_result: Ok(()),
+                focus_result: Ok(()),
+            }
+        }
+    }
+
+    impl WindowOps for FakeWindow {
+        fn show(&self) -> Result<(), String> {
+            self.show_result.clone()
+        }
+        fn hide(&self) -> Result<(), String> {
+            self.hide_result.clone()
+        }
+        fn focus(&self) -> Result<(), String> {
+            self.focus_result.clone()
+        }
+    }
+
+    #[test]
+    fn run_window_action_invokes_action_on_the_present_window() {
+        let result = run_window_action(Some(FakeWindow::default()), |w| w.show());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn run_window_action_reports_not_found_when_window_is_missing() {
+        let result = run_window_action(None::<FakeWindow>, |w| w.show());
+        assert!(matches!(result, Err(WindowActionError::NotFound)));
+    }
+
+    #[test]
+    fn run_window_action_propagates_the_action_failure() {
+        let window = FakeWindow { hide_result: Err("hide boom".to_string()), ..Default::default() };
+        let result = run_window_action(Some(window), |w| w.hide());
+        assert!(matches!(result, Err(WindowActionError::Failed(e)) if e == "hide boom"));
+    }
+
+    #[test]
+    fn window_action_response_maps_each_outcome_to_its_status() {
+        use actix_web::http::StatusCode;
+
+        assert_eq!(window_action_response(Ok(())).status(), StatusCode::OK);
+        assert_eq!(window_action_response(Err(WindowActionError::NotFound)).status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            window_action_response(Err(WindowActionError::Failed("boom".to_string()))).status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
+
+#[cfg(all(test, feature = "actix-web-server"))]
+mod backend_restart_tests {
+    use super::*;
+    use std::cell::Cell;
+
+    struct FakeBackendControl {
+        stop_result: Result<(), String>,
+        start_result: Result<(), String>,
+        start_called: Cell<bool>,
+    }
+
+    impl Default for FakeBackendControl {
+        fn default() -> Self {
+            Self {
+                stop_result: Ok(()),
+                start_result: Ok(()),
+                start_called: Cell::new(false),
+            }
+        }
+    }
+
+    impl BackendControl for FakeBackendControl {
+        async fn stop(&self) -> Result<(), String> {
+            self.stop_result.clone()
+        }
+        async fn start(&self) -> Result<(), String> {
+            self.start_called.set(true);
+            self.start_result.clone()
+        }
+    }
+
+    #[actix_web::test]
+    async fn restart_backend_starts_after_a_successful_stop() {
+        let control = FakeBackendControl::default();
+        assert!(restart_backend(&control).await.is_ok());
+        assert!(control.start_called.get());
+    }
+
+    #[actix_web::test]
+    async fn restart_backend_skips_start_when_stop_fails() {
+        let control = FakeBackendControl {
+            stop_result: Err("stop boom".to_string()),
+            ..Default::default()
+        };
+        let err = restart_backend(&control).await.unwrap_err();
+        assert!(err.contains("failed to stop backend"));
+        assert!(err.contains("stop boom"));
+        assert!(!control.start_called.get());
+    }
+
+    #[actix_web::test]
+    async fn restart_backend_reports_a_start_failure() {
+        let control = FakeBackendControl {
+            start_result: Err("start boom".to_string()),
+            ..Default::default()
+        };
+        let err = restart_backend(&control).await.unwrap_err();
+        assert!(err.contains("failed to start backend"));
+        assert!(err.contains("start boom"));
+    }
+}
+
+#[cfg(test)]
+mod server_mode_tests {
+    use super::*;
+
+    #[test]
+    fn parses_canonical_and_short_names() {
+        assert_eq!(ServerMode::parse("embedded-server"), Some(ServerMode::Embedded));
+        assert_eq!(ServerMode::parse("embedded"), Some(ServerMode::Embedded));
+        assert_eq!(ServerMode::parse("external-server"), Some(ServerMode::External));
+        assert_eq!(ServerMode::parse("external"), Some(ServerMode::External));
+        assert_eq!(ServerMode::parse("no-server"), Some(ServerMode::NoServer));
+        assert_eq!(ServerMode::parse("none"), Some(ServerMode::NoServer));
+        assert_eq!(ServerMode::parse("bogus"), None);
+    }
+
+    fn cli(overrides: impl FnOnce(&mut CliArgs)) -> CliArgs {
+        let mut args = CliArgs {
+            web_port: None,
+            backend_port: None,
+            bind: None,
+            no_backend: false,
+            external_backend: None,
+            external_web_url: None,
+            server_mode: None,
+        };
+        overrides(&mut args);
+        args
+    }
+
+    #[test]
+    fn resolve_defaults_to_compile_time_mode() {
+        let config = AppConfig::resolve(FileConfig::default(), cli(|_| {}));
+        assert_eq!(config.mode, ServerMode::compile_time_default());
+    }
+
+    #[test]
+    fn resolve_infers_no_server_from_no_backend_flag() {
+        let config = AppConfig::resolve(FileConfig::default(), cli(|a| a.no_backend = true));
+        assert_eq!(config.mode, ServerMode::NoServer);
+    }
+
+    #[test]
+    fn resolve_infers_external_from_external_backend() {
+        let config = AppConfig::resolve(
+            FileConfig::default(),
+            cli(|a| a.external_backend = Some("http://example.com/graphql".to_string())),
+        );
+        assert_eq!(config.mode, ServerMode::External);
+    }
+
+    #[test]
+    fn resolve_honors_explicit_server_mode_over_inference() {
+        let config = AppConfig::resolve(
+            FileConfig::default(),
+            cli(|a| {
+                a.no_backend = true;
+                a.server_mode = Some("embedded-server".to_string());
+            }),
+        );
+        assert_eq!(config.mode, ServerMode::Embedded);
+    }
+
+    #[test]
+    fn resolve_falls_back_on_unknown_server_mode() {
+        let config = AppConfig::resolve(
+            FileConfig::default(),
+            cli(|a| a.server_mode = Some("not-a-real-mode".to_string())),
+        );
+        assert_eq!(config.mode, ServerMode::compile_time_default());
+    }
+}
+
+#[cfg(test)]
+mod asset_path_safety_tests {
+    use super::*;
+    use std::fs;
+
+    /// A fresh, empty directory under the OS temp dir, unique to this test
+    /// run and process so parallel `cargo test` runs don't collide.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "dipeo-desktop-test-{}-{}-{:p}",
+            name,
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("failed to create scratch dir");
+        dir
+    }
+
+    fn asset_request(path: &str) -> tauri::http::Request<Vec<u8>> {
+        tauri::http::Request::builder().uri(path).body(Vec::new()).unwrap()
+    }
+
+    #[test]
+    fn path_is_within_allows_a_nested_asset() {
+        let web_dir = scratch_dir("within-nested");
+        fs::write(web_dir.join("app.js"), b"// ok").unwrap();
+
+        assert!(path_is_within(&web_dir, &web_dir.join("app.js")));
+
+        fs::remove_dir_all(&web_dir).ok();
+    }
+
+    #[test]
+    fn path_is_within_rejects_parent_traversal() {
+        let root = scratch_dir("within-traversal");
+        let web_dir = root.join("web");
+        fs::create_dir_all(&web_dir).unwrap();
+        fs::write(root.join("secret.txt"), b"top secret").unwrap();
+
+        let traversal = web_dir.join("..").join("secret.txt");
+        assert!(!path_is_within(&web_dir, &traversal));
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn path_is_within_rejects_an_absolute_candidate_outside_root() {
+        let web_dir = scratch_dir("within-absolute");
+        // Simulates what a Windows drive-rooted request path (`C:\Windows\
+        // win.ini`) does to `PathBuf::join` - it silently discards `root`
+        // and resolves to an absolute path outside it.
+        let outside = std::env::temp_dir();
+
+        assert!(!path_is_within(&web_dir, &outside));
+
+        fs::remove_dir_all(&web_dir).ok();
+    }
+
+    #[test]
+    fn serve_web_asset_serves_an_existing_file() {
+        let web_dir = scratch_dir("serve-existing");
+        fs::write(web_dir.join("index.html"), b"<html>index</html>").unwrap();
+        fs::write(web_dir.join("app.js"), b"console.log(1)").unwrap();
+
+        let response = serve_web_asset(&web_dir, &asset_request("/app.js"));
+        assert_eq!(response.body(), b"console.log(1)");
+
+        fs::remove_dir_all(&web_dir).ok();
+    }
+
+    #[test]
+    fn serve_web_asset_falls_back_to_index_for_a_traversal_attempt() {
+        let root = scratch_dir("serve-traversal-root");
+        let web_dir = root.join("web");
+        fs::create_dir_all(&web_dir).unwrap();
+        fs::write(web_dir.join("index.html"), b"<html>index</html>").unwrap();
+        fs::write(root.join("secret.txt"), b"top secret").unwrap();
+
+        let response = serve_web_asset(&web_dir, &asset_request("/../secret.txt"));
+        assert_eq!(response.body(), b"<html>index</html>");
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn serve_web_asset_falls_back_to_index_for_an_unknown_path() {
+        let web_dir = scratch_dir("serve-unknown");
+        fs::write(web_dir.join("index.html"), b"<html>index</html>").unwrap();
+
+        let response = serve_web_asset(&web_dir, &asset_request("/does-not-exist.js"));
+        assert_eq!(response.body(), b"<html>index</html>");
+
+        fs::remove_dir_all(&web_dir).ok();
+    }
 }
\ No newline at end of file